@@ -1,60 +1,202 @@
-use crate::frame::{Frame, FrameHeader, FRAME_END};
+use crate::frame::{
+    Frame, FrameHeader, Open, ProtocolHeader, StartOk, TuneOk, DEFAULT_CONN_CHANNEL, FRAME_END,
+};
 
 use amqp_serde::{to_buffer, types::AmqpChannelId};
-use bytes::{Buf, BytesMut};
+use bytes::{Buf, Bytes, BytesMut};
 use serde::Serialize;
-use std::io;
+use std::{collections::VecDeque, io, path::Path, sync::Arc, time::Duration};
 use tokio::{
-    io::{AsyncReadExt, AsyncWriteExt},
+    io::{duplex, split, AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt, DuplexStream, ReadHalf, WriteHalf},
     net::{
         tcp::{OwnedReadHalf, OwnedWriteHalf},
         TcpStream,
     },
+    time::{sleep, Instant},
+};
+#[cfg(unix)]
+use tokio::net::{
+    unix::{OwnedReadHalf as UnixOwnedReadHalf, OwnedWriteHalf as UnixOwnedWriteHalf},
+    UnixStream,
 };
+use tokio_rustls::{client::TlsStream, rustls, TlsConnector};
 #[cfg(feature = "tracing")]
 use tracing::trace;
+// spans here are plain `tracing` spans, not a direct OpenTelemetry SDK
+// integration; export them via `tracing-opentelemetry` (or any other
+// OTel-compatible subscriber layer) to get them into a trace backend
+#[cfg(feature = "telemetry")]
+use tracing::Instrument;
 
 use super::Error;
 type Result<T> = std::result::Result<T, Error>;
 const DEFAULT_BUFFER_SIZE: usize = 8192;
+// matches the `frame_max` RabbitMQ itself proposes in `Tune` by default
+const DEFAULT_MAX_FRAME_SIZE: usize = 131_072;
+// a write that would push `BufWriter::in_flight` past this is rejected
+// instead of tracked, so a broker that never acknowledges anything (or a bug
+// in the ack wiring) can't drive unbounded memory growth on the write side
+const DEFAULT_MAX_IN_FLIGHT_FRAMES: usize = 4096;
+
+// `TlsStream` does not expose an owned split like `TcpStream::into_split`, so
+// the halves produced by `tokio::io::split` are used instead.
+type TlsReadHalf = ReadHalf<TlsStream<TcpStream>>;
+type TlsWriteHalf = WriteHalf<TlsStream<TcpStream>>;
+
+/// Initial buffer capacities and the maximum frame size `BufReader` will
+/// buffer before giving up on a peer, passed to [`SplitConnection::with_config`].
+/// `max_frame_size` is only the bound used before a handshake completes;
+/// once `Tune`/`TuneOk` negotiate a `frame_max`, callers should update it via
+/// [`SplitConnection::set_max_frame_size`].
+#[derive(Clone, Copy)]
+pub(crate) struct ConnectionConfig {
+    pub read_buffer_capacity: usize,
+    pub write_buffer_capacity: usize,
+    pub max_frame_size: usize,
+}
+
+impl Default for ConnectionConfig {
+    fn default() -> Self {
+        Self {
+            read_buffer_capacity: DEFAULT_BUFFER_SIZE,
+            write_buffer_capacity: DEFAULT_BUFFER_SIZE,
+            max_frame_size: DEFAULT_MAX_FRAME_SIZE,
+        }
+    }
+}
 
-pub(crate) struct SplitConnection {
-    reader: BufReader,
-    writer: BufWriter,
+pub(crate) struct SplitConnection<R = OwnedReadHalf, W = OwnedWriteHalf> {
+    reader: BufReader<R>,
+    writer: BufWriter<W>,
 }
-pub(crate) struct BufReader {
-    stream: OwnedReadHalf,
+pub(crate) struct BufReader<R> {
+    stream: R,
     buffer: BytesMut,
+    // a frame whose declared payload size exceeds this is rejected instead of
+    // buffered, so a buggy/malicious peer can't drive unbounded memory growth
+    max_frame_size: usize,
 }
-pub(crate) struct BufWriter {
-    stream: OwnedWriteHalf,
+pub(crate) struct BufWriter<W> {
+    stream: W,
     buffer: BytesMut,
+    // frames handed to `write_frame` that have not yet been dropped by
+    // `ack()`, kept so `ReconnectingConnection` can replay them after a
+    // reconnect. Bounded by `max_in_flight` so a broker that never
+    // acknowledges anything can't grow this without limit.
+    in_flight: VecDeque<(AmqpChannelId, Bytes)>,
+    max_in_flight: usize,
+    // when `true`, `write_frame` only flushes once `buffer` crosses
+    // `high_water_mark`, instead of after every frame
+    batching: bool,
+    high_water_mark: usize,
+    // how long a buffered-but-unflushed frame is allowed to sit before
+    // `flush_on_idle` drains it, bounding latency during low-throughput
+    // periods while batching is on
+    coalesce_window: Duration,
+    // when the oldest currently-buffered frame was added; `None` when
+    // `buffer` is empty
+    buffered_since: Option<Instant>,
 }
 
+const DEFAULT_BATCH_HIGH_WATER: usize = 64 * 1024;
+const DEFAULT_COALESCE_WINDOW: Duration = Duration::from_millis(5);
+
 // Support to split socket connection into reader half and wirter half, which can be run in different tasks cocurrently
 // Same interfaces to read/write packet before and after split.
 impl SplitConnection {
     pub async fn open(addr: &str) -> Result<Self> {
+        Self::with_config(addr, ConnectionConfig::default()).await
+    }
+
+    /// Open a connection with explicit buffer capacities and a maximum frame
+    /// size, instead of the defaults `open` uses.
+    pub async fn with_config(addr: &str, config: ConnectionConfig) -> Result<Self> {
+        let stream = TcpStream::connect(addr).await?;
+        let (reader, writer) = stream.into_split();
+
+        Ok(Self::from_halves_with_config(reader, writer, config))
+    }
+}
+
+impl SplitConnection<TlsReadHalf, TlsWriteHalf> {
+    /// Open a TLS (amqps) connection. `server_name` is used for both SNI and
+    /// certificate hostname verification; `tls_config` carries the root store
+    /// (native-roots or webpki-roots) and any negotiated ALPN protocols.
+    pub async fn open_tls(
+        addr: &str,
+        server_name: &str,
+        tls_config: Arc<rustls::ClientConfig>,
+    ) -> Result<Self> {
         let stream = TcpStream::connect(addr).await?;
+
+        let domain = rustls::ServerName::try_from(server_name)
+            .map_err(|err| io::Error::new(io::ErrorKind::InvalidInput, err))?;
+        let stream = TlsConnector::from(tls_config).connect(domain, stream).await?;
+        let (reader, writer) = split(stream);
+
+        Ok(Self::from_halves(reader, writer))
+    }
+}
+
+#[cfg(unix)]
+impl SplitConnection<UnixOwnedReadHalf, UnixOwnedWriteHalf> {
+    /// Open a connection over a Unix domain socket, e.g. for brokers reachable
+    /// via a local socket or a sidecar/proxy deployment.
+    pub async fn open_uds(path: impl AsRef<Path>) -> Result<Self> {
+        let stream = UnixStream::connect(path).await?;
         let (reader, writer) = stream.into_split();
 
-        let read_buffer = BytesMut::with_capacity(DEFAULT_BUFFER_SIZE);
-        let write_buffer = BytesMut::with_capacity(DEFAULT_BUFFER_SIZE);
+        Ok(Self::from_halves(reader, writer))
+    }
+}
+
+impl SplitConnection<ReadHalf<DuplexStream>, WriteHalf<DuplexStream>> {
+    /// Create a connected, in-memory pair of connections backed by
+    /// `tokio::io::duplex`, so handshake/frame-level tests can run against a
+    /// scripted fake peer instead of a real broker on `localhost:5672`.
+    pub(crate) fn pair(capacity: usize) -> (Self, Self) {
+        let (client, server) = duplex(capacity);
+        let (client_reader, client_writer) = split(client);
+        let (server_reader, server_writer) = split(server);
+
+        (
+            Self::from_halves(client_reader, client_writer),
+            Self::from_halves(server_reader, server_writer),
+        )
+    }
+}
+
+impl<R, W> SplitConnection<R, W>
+where
+    R: AsyncRead + Unpin,
+    W: AsyncWrite + Unpin,
+{
+    fn from_halves(reader: R, writer: W) -> Self {
+        Self::from_halves_with_config(reader, writer, ConnectionConfig::default())
+    }
 
-        Ok(Self {
+    fn from_halves_with_config(reader: R, writer: W, config: ConnectionConfig) -> Self {
+        Self {
             reader: BufReader {
                 stream: reader,
-                buffer: read_buffer,
+                buffer: BytesMut::with_capacity(config.read_buffer_capacity),
+                max_frame_size: config.max_frame_size,
             },
             writer: BufWriter {
                 stream: writer,
-                buffer: write_buffer,
+                buffer: BytesMut::with_capacity(config.write_buffer_capacity),
+                in_flight: VecDeque::new(),
+                max_in_flight: DEFAULT_MAX_IN_FLIGHT_FRAMES,
+                batching: false,
+                high_water_mark: DEFAULT_BATCH_HIGH_WATER,
+                coalesce_window: DEFAULT_COALESCE_WINDOW,
+                buffered_since: None,
             },
-        })
+        }
     }
 
     /// split connection into reader half and writer half
-    pub(crate) fn into_split(self) -> (BufReader, BufWriter) {
+    pub(crate) fn into_split(self) -> (BufReader<R>, BufWriter<W>) {
         (self.reader, self.writer)
     }
 
@@ -74,12 +216,65 @@ impl SplitConnection {
         self.writer.write_frame(channel, frame).await
     }
 
+    /// like [`Self::write_frame`], but the frame is never tracked as
+    /// in-flight, so it is excluded from `unacked_frames` and will not be
+    /// replayed on reconnect; used for the handshake frames themselves,
+    /// which would violate the protocol if resent mid-session
+    pub(crate) async fn write_frame_untracked(&mut self, channel: AmqpChannelId, frame: Frame) -> Result<usize> {
+        self.writer.write_frame_untracked(channel, frame).await
+    }
+
     pub async fn read_frame(&mut self) -> Result<ChannelFrame> {
         self.reader.read_frame().await
     }
+
+    /// frames handed to [`Self::write_frame`] that the broker has not yet
+    /// acknowledged, used by `ReconnectingConnection` to replay them
+    pub(crate) fn unacked_frames(&self) -> impl Iterator<Item = &(AmqpChannelId, Bytes)> {
+        self.writer.unacked()
+    }
+
+    /// drop the oldest `count` in-flight frames once the broker has
+    /// acknowledged them. Not called automatically from `read_frame`: this
+    /// layer has no notion of which incoming frame confirms which write, so
+    /// it is left to a caller that does (e.g. a confirm-aware channel layer).
+    #[allow(dead_code, /*used for testing only*/)]
+    pub(crate) fn ack_frames(&mut self, count: usize) {
+        self.writer.ack(count)
+    }
+
+    // resend an already-encoded frame as-is, without re-running it through
+    // `write_frame`'s encoding step
+    pub(crate) async fn write_raw(&mut self, channel: AmqpChannelId, bytes: Bytes) -> Result<usize> {
+        self.writer.write_raw(channel, bytes).await
+    }
+
+    /// opt the writer into batching `write_frame` calls, see
+    /// [`BufWriter::enable_batching`]
+    pub(crate) fn enable_write_batching(&mut self, high_water_mark: usize, coalesce_window: Duration) {
+        self.writer.enable_batching(high_water_mark, coalesce_window)
+    }
+
+    /// flush any frames accumulated while in batching mode
+    pub async fn flush(&mut self) -> Result<()> {
+        self.writer.flush().await
+    }
+
+    /// wait until the oldest buffered-but-unflushed frame has sat for
+    /// `coalesce_window`, then flush; see [`BufWriter::flush_on_idle`]
+    pub(crate) async fn flush_on_idle(&mut self) -> Result<()> {
+        self.writer.flush_on_idle().await
+    }
+
+    /// update the maximum frame size `BufReader` accepts, e.g. once the
+    /// handshake negotiates a different `frame_max` than the configured
+    /// default
+    pub(crate) fn set_max_frame_size(&mut self, max_frame_size: usize) {
+        self.reader.set_max_frame_size(max_frame_size)
+    }
 }
 
-impl BufWriter {
+impl<W: AsyncWrite + Unpin> BufWriter<W> {
     // write any serializable value to socket
     pub async fn write<T: Serialize>(&mut self, value: &T) -> Result<usize> {
         to_buffer(value, &mut self.buffer)
@@ -92,10 +287,62 @@ impl BufWriter {
 
     // write a AMQP frame over a specific channel
     pub async fn write_frame(&mut self, channel: AmqpChannelId, frame: Frame) -> Result<usize> {
+        #[cfg(feature = "telemetry")]
+        {
+            let span = tracing::info_span!(
+                "amqp.frame.send",
+                channel,
+                frame.frame_type = frame.get_frame_type(),
+                frame.payload_size = tracing::field::Empty,
+            );
+            return self.write_frame_encoded(channel, frame, true).instrument(span).await;
+        }
+        #[cfg(not(feature = "telemetry"))]
+        {
+            self.write_frame_encoded(channel, frame, true).await
+        }
+    }
+
+    /// like [`Self::write_frame`], but the frame is never tracked as
+    /// in-flight; see [`SplitConnection::write_frame_untracked`]
+    pub(crate) async fn write_frame_untracked(&mut self, channel: AmqpChannelId, frame: Frame) -> Result<usize> {
+        #[cfg(feature = "telemetry")]
+        {
+            let span = tracing::info_span!(
+                "amqp.frame.send",
+                channel,
+                frame.frame_type = frame.get_frame_type(),
+                frame.payload_size = tracing::field::Empty,
+            );
+            return self.write_frame_encoded(channel, frame, false).instrument(span).await;
+        }
+        #[cfg(not(feature = "telemetry"))]
+        {
+            self.write_frame_encoded(channel, frame, false).await
+        }
+    }
+
+    async fn write_frame_encoded(&mut self, channel: AmqpChannelId, frame: Frame, track: bool) -> Result<usize> {
         // TODO: tracing
         #[cfg(feature = "tracing")]
         trace!("SENT on channel {}: {}", channel, frame);
 
+        if track && self.in_flight.len() >= self.max_in_flight {
+            return Err(io::Error::new(
+                io::ErrorKind::Other,
+                format!(
+                    "{} frames in flight without an ack, exceeding max_in_flight {}",
+                    self.in_flight.len(),
+                    self.max_in_flight
+                ),
+            )
+            .into());
+        }
+
+        // offset of this frame within `buffer`: in batching mode, prior
+        // frames may still be sitting there unflushed
+        let start = self.buffer.len();
+
         // reserve bytes for frame header, which to be updated after encoding payload
         let header = FrameHeader {
             frame_type: frame.get_frame_type(),
@@ -109,20 +356,128 @@ impl BufWriter {
 
         // update frame's payload size
         for (i, v) in (payload_size as u32).to_be_bytes().iter().enumerate() {
-            let p = self.buffer.get_mut(i + 3).unwrap();
+            let p = self.buffer.get_mut(start + i + 3).unwrap();
             *p = *v;
         }
 
         // encode frame end byte
         to_buffer(&FRAME_END, &mut self.buffer).unwrap();
 
-        // flush whole buffer
-        self.stream.write_all(&self.buffer).await?;
+        let len = self.buffer.len() - start;
+        #[cfg(feature = "telemetry")]
+        tracing::Span::current().record("frame.payload_size", payload_size);
 
-        // discard sent data in write buffer
-        let len = self.buffer.len();
-        self.buffer.advance(len);
+        if track {
+            // keep a copy so `ReconnectingConnection` can replay it if the
+            // broker never acknowledged it before the link dropped
+            self.in_flight
+                .push_back((channel, Bytes::copy_from_slice(&self.buffer[start..])));
+        }
+
+        // auto-flush unless batching mode is opted into, or the accumulated
+        // buffer has crossed the high-water mark
+        if !self.batching || self.buffer.len() >= self.high_water_mark {
+            self.flush().await?;
+        } else if self.buffered_since.is_none() {
+            // first frame to land in an empty, batching buffer: start the
+            // coalescing window so `flush_on_idle` has something to wait on
+            self.buffered_since = Some(Instant::now());
+        }
+
+        Ok(len)
+    }
+
+    /// Accumulate frames in `buffer` instead of flushing after every
+    /// `write_frame`, draining them together once `high_water_mark` bytes
+    /// have been buffered, [`BufWriter::flush`] is called explicitly, or
+    /// `coalesce_window` has elapsed since the buffer went from empty to
+    /// non-empty, whichever comes first. The writer task should race
+    /// [`BufWriter::flush_on_idle`] against its next inbound write, e.g.:
+    ///
+    /// ```ignore
+    /// loop {
+    ///     tokio::select! {
+    ///         next = rx.recv() => writer.write_frame(channel, next).await?,
+    ///         result = writer.flush_on_idle() => { result?; }
+    ///     }
+    /// }
+    /// ```
+    pub(crate) fn enable_batching(&mut self, high_water_mark: usize, coalesce_window: Duration) {
+        self.batching = true;
+        self.high_water_mark = high_water_mark;
+        self.coalesce_window = coalesce_window;
+    }
+
+    /// flush any frames accumulated while in batching mode
+    pub async fn flush(&mut self) -> Result<()> {
+        if !self.buffer.is_empty() {
+            // `write_all_buf` drains via vectored writes where the stream
+            // supports it, and advances `buffer` as it goes
+            self.stream.write_all_buf(&mut self.buffer).await?;
+        }
+        self.buffered_since = None;
+        Ok(())
+    }
+
+    // how long until the oldest buffered frame's coalescing window elapses;
+    // `None` when nothing is buffered, so `flush_on_idle` has nothing to wait on
+    fn time_until_flush(&self) -> Option<Duration> {
+        let buffered_since = self.buffered_since?;
+        Some(
+            self.coalesce_window
+                .saturating_sub(buffered_since.elapsed()),
+        )
+    }
+
+    /// Wait until `coalesce_window` has elapsed since the buffer went from
+    /// empty to non-empty, then flush. Resolves immediately if the window
+    /// has already elapsed; never resolves while nothing is buffered, so it
+    /// is meant to be raced inside a `select!`
+    /// against whatever produces the next frame, not awaited standalone.
+    pub(crate) async fn flush_on_idle(&mut self) -> Result<()> {
+        match self.time_until_flush() {
+            Some(remaining) => {
+                sleep(remaining).await;
+                self.flush().await
+            }
+            None => std::future::pending().await,
+        }
+    }
+
+    /// frames written but not yet dropped by [`BufWriter::ack`]
+    pub(crate) fn unacked(&self) -> impl Iterator<Item = &(AmqpChannelId, Bytes)> {
+        self.in_flight.iter()
+    }
+
+    /// drop the oldest `count` in-flight frames once the broker has
+    /// acknowledged them
+    #[allow(dead_code, /*used for testing only*/)]
+    pub(crate) fn ack(&mut self, count: usize) {
+        for _ in 0..count.min(self.in_flight.len()) {
+            self.in_flight.pop_front();
+        }
+    }
+
+    // resend an already-encoded frame, e.g. when replaying in-flight frames
+    // after a reconnect; tracked as in-flight again until acked. Subject to
+    // the same `max_in_flight` bound as `write_frame_encoded`, so a replay
+    // can't grow `in_flight` past the limit either.
+    async fn write_raw(&mut self, channel: AmqpChannelId, bytes: Bytes) -> Result<usize> {
+        if self.in_flight.len() >= self.max_in_flight {
+            return Err(io::Error::new(
+                io::ErrorKind::Other,
+                format!(
+                    "{} frames in flight without an ack, exceeding max_in_flight {}",
+                    self.in_flight.len(),
+                    self.max_in_flight
+                ),
+            )
+            .into());
+        }
 
+        self.stream.write_all(&bytes).await?;
+        let len = bytes.len();
+        self.in_flight.push_back((channel, bytes));
         Ok(len)
     }
 
@@ -135,11 +490,34 @@ impl BufWriter {
 
 type ChannelFrame = (AmqpChannelId, Frame);
 
-impl BufReader {
+impl<R: AsyncRead + Unpin> BufReader<R> {
     // try to decode a whole frame from the bufferred data.
     // If it is incomplete data, return None;
     // If the frame syntax is corrupted, return Error.
     fn decode(&mut self) -> Result<Option<ChannelFrame>> {
+        // peek the frame header's `payload_size` (bytes 3..7, see
+        // `BufWriter::write_frame_encoded`) before handing the buffer to the
+        // full decoder, so an oversized frame is rejected instead of
+        // buffered indefinitely. `Frame::decode` can only succeed once at
+        // least these 7 header bytes are present, so this is also the value
+        // to report on the matching span below.
+        let mut declared_payload_size = None;
+        if self.buffer.len() >= 7 {
+            let payload_size =
+                u32::from_be_bytes(self.buffer[3..7].try_into().unwrap()) as usize;
+            if payload_size > self.max_frame_size {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    format!(
+                        "frame payload size {payload_size} exceeds max_frame_size {}",
+                        self.max_frame_size
+                    ),
+                )
+                .into());
+            }
+            declared_payload_size = Some(payload_size);
+        }
+
         match Frame::decode(&self.buffer)? {
             Some((len, channel_id, frame)) => {
                 // discard parsed data in read buffer
@@ -147,6 +525,13 @@ impl BufReader {
                 // TODO: tracing
                 #[cfg(feature = "tracing")]
                 trace!("RECV on channel {}: {}", channel_id, frame);
+                #[cfg(feature = "telemetry")]
+                tracing::Span::current()
+                    .record("channel", channel_id)
+                    .record("frame.frame_type", frame.get_frame_type())
+                    // match `BufWriter::write_frame_encoded`, which records
+                    // the payload alone, not the full header+payload+end frame
+                    .record("frame.payload_size", declared_payload_size.unwrap_or(len));
                 Ok(Some((channel_id, frame)))
             }
             None => Ok(None),
@@ -155,6 +540,23 @@ impl BufReader {
 
     // Read a complete frame from socket connection, return channel id and decoded frame.
     pub async fn read_frame(&mut self) -> Result<ChannelFrame> {
+        #[cfg(feature = "telemetry")]
+        {
+            let span = tracing::info_span!(
+                "amqp.frame.recv",
+                channel = tracing::field::Empty,
+                frame.frame_type = tracing::field::Empty,
+                frame.payload_size = tracing::field::Empty,
+            );
+            return self.read_frame_decoded().instrument(span).await;
+        }
+        #[cfg(not(feature = "telemetry"))]
+        {
+            self.read_frame_decoded().await
+        }
+    }
+
+    async fn read_frame_decoded(&mut self) -> Result<ChannelFrame> {
         // check if there is remaining data in buffer to decode first
         let result = self.decode()?;
         if let Some(frame) = result {
@@ -183,6 +585,278 @@ impl BufReader {
 
     // do nothing except consume the reader itself
     pub async fn close(self) {}
+
+    /// update the maximum frame size accepted by `decode`, e.g. once the
+    /// handshake negotiates a different `frame_max` than the configured
+    /// default
+    pub(crate) fn set_max_frame_size(&mut self, max_frame_size: usize) {
+        self.max_frame_size = max_frame_size;
+    }
+}
+
+/////////////////////////////////////////////////////////////////////////////
+
+/// Credentials needed to redo the Start/StartOk/Tune/TuneOk/Open handshake
+/// after reconnecting, kept around by [`ReconnectingConnection`].
+#[derive(Clone)]
+pub(crate) struct HandshakeCredentials {
+    pub mechanism: String,
+    pub response: String,
+    pub locale: String,
+}
+
+/// Retry policy for re-dialing the broker after the link drops.
+#[derive(Clone, Copy)]
+pub(crate) struct ReconnectBackoff {
+    pub max_retries: u32,
+    pub base_delay: Duration,
+    pub max_delay: Duration,
+}
+
+impl Default for ReconnectBackoff {
+    fn default() -> Self {
+        Self {
+            max_retries: 5,
+            base_delay: Duration::from_millis(200),
+            max_delay: Duration::from_secs(30),
+        }
+    }
+}
+
+impl ReconnectBackoff {
+    fn delay_for(&self, attempt: u32) -> Duration {
+        let factor = 1u32.checked_shl(attempt).unwrap_or(u32::MAX);
+        self.base_delay.saturating_mul(factor).min(self.max_delay)
+    }
+}
+
+/// A [`SplitConnection`] wrapper that transparently re-dials the broker and
+/// redoes the connection-level handshake when `read_frame`/`write_frame`
+/// report the link was dropped (`Error::Interrupted` / `Error::CloseCallbackError`,
+/// both derived from a read hitting EOF). A write that fails directly (e.g.
+/// `write_all`/`flush` returning a plain I/O error) is *not* currently
+/// classified as a dropped link and so does not trigger a reconnect attempt;
+/// it propagates to the caller as-is.
+///
+/// Frames written but not yet acked via [`SplitConnection::ack_frames`] are
+/// replayed after a successful reconnect. Since nothing here infers an ack
+/// from reads (see [`ReconnectingConnection::read_frame`]), replay is
+/// best-effort at-least-once, not exactly-once: a write the broker actually
+/// processed before the drop may still be resent if it was never explicitly
+/// acked.
+///
+/// `on_reconnect` is invoked with the channel ids that were open before the
+/// drop; this type only redoes the *connection*-level handshake, it does not
+/// resend `Channel.Open` for them; reopening is delegated entirely to the
+/// `on_reconnect` callback.
+///
+/// Generic over the transport like [`SplitConnection`] so the bookkeeping
+/// (`note_channel_open`/`note_channel_close`/`on_reconnect`/replay) can be
+/// exercised in tests against [`SplitConnection::pair`] without a real dial;
+/// actually re-dialing only makes sense for a real socket, so `open` and
+/// `reconnect` are only implemented for the default, TCP-backed instantiation.
+pub(crate) struct ReconnectingConnection<R = OwnedReadHalf, W = OwnedWriteHalf> {
+    addr: String,
+    credentials: HandshakeCredentials,
+    backoff: ReconnectBackoff,
+    connection: SplitConnection<R, W>,
+    open_channels: Vec<AmqpChannelId>,
+    on_reconnect: Option<Box<dyn FnMut(&[AmqpChannelId]) + Send>>,
+}
+
+impl<R, W> ReconnectingConnection<R, W>
+where
+    R: AsyncRead + Unpin,
+    W: AsyncWrite + Unpin,
+{
+    /// register a callback invoked with the previously-open channel ids after
+    /// a successful reconnect; the callback is responsible for actually
+    /// reissuing `Channel.Open` for each one, this type only restores the
+    /// underlying connection
+    pub(crate) fn on_reconnect(&mut self, callback: impl FnMut(&[AmqpChannelId]) + Send + 'static) {
+        self.on_reconnect = Some(Box::new(callback));
+    }
+
+    /// track a channel as open so it is reported to `on_reconnect` after a drop
+    pub(crate) fn note_channel_open(&mut self, channel: AmqpChannelId) {
+        if !self.open_channels.contains(&channel) {
+            self.open_channels.push(channel);
+        }
+    }
+
+    pub(crate) fn note_channel_close(&mut self, channel: AmqpChannelId) {
+        self.open_channels.retain(|id| *id != channel);
+    }
+
+    /// adopt `connection` as the live connection, replaying writes the broker
+    /// never acknowledged before the drop, then notify `on_reconnect`. Split
+    /// out of `reconnect` so the replay/notify bookkeeping can be driven
+    /// directly in tests, without a real dial.
+    async fn finish_reconnect(&mut self, connection: SplitConnection<R, W>) -> Result<()> {
+        let unacked: Vec<_> = self
+            .connection
+            .unacked_frames()
+            .map(|(channel, bytes)| (*channel, bytes.clone()))
+            .collect();
+        self.connection = connection;
+        for (channel, bytes) in unacked {
+            self.connection.write_raw(channel, bytes).await?;
+        }
+
+        if let Some(callback) = self.on_reconnect.as_mut() {
+            callback(&self.open_channels);
+        }
+
+        Ok(())
+    }
+
+    /// close the underlying connection, clearing the open-channel bookkeeping
+    /// first since nothing is open once the socket is gone
+    #[allow(dead_code, /*used for testing only*/)]
+    pub(crate) async fn close(mut self) -> Result<()> {
+        for channel in self.open_channels.clone() {
+            self.note_channel_close(channel);
+        }
+        self.connection.close().await
+    }
+}
+
+impl ReconnectingConnection {
+    pub(crate) async fn open(
+        addr: &str,
+        credentials: HandshakeCredentials,
+        backoff: ReconnectBackoff,
+    ) -> Result<Self> {
+        let mut connection = SplitConnection::open(addr).await?;
+        Self::handshake(&mut connection, &credentials).await?;
+
+        let mut this = Self {
+            addr: addr.to_string(),
+            credentials,
+            backoff,
+            connection,
+            open_channels: Vec::new(),
+            on_reconnect: None,
+        };
+        this.note_channel_open(DEFAULT_CONN_CHANNEL);
+        Ok(this)
+    }
+
+    pub(crate) async fn write_frame(&mut self, channel: AmqpChannelId, frame: Frame) -> Result<usize> {
+        match self.connection.write_frame(channel, frame.clone()).await {
+            Ok(len) => Ok(len),
+            Err(Error::Interrupted | Error::CloseCallbackError) => {
+                self.reconnect().await?;
+                self.connection.write_frame(channel, frame).await
+            }
+            Err(err) => Err(err),
+        }
+    }
+
+    pub(crate) async fn read_frame(&mut self) -> Result<ChannelFrame> {
+        // deliberately does *not* infer an ack from every successful read:
+        // AMQP delivers unsolicited frames on this channel too (heartbeats,
+        // `Connection.Blocked`, a server-initiated `Close`), and under
+        // publisher confirms reads and writes aren't 1:1 either, so a
+        // read-count heuristic would drop still-unconfirmed writes from the
+        // replay set. Acking is left to callers that know which write an
+        // incoming frame actually confirms (via `SplitConnection::ack_frames`),
+        // which makes replay best-effort at-least-once rather than exact.
+        match self.connection.read_frame().await {
+            Ok(frame) => Ok(frame),
+            Err(Error::Interrupted | Error::CloseCallbackError) => {
+                self.reconnect().await?;
+                self.connection.read_frame().await
+            }
+            Err(err) => Err(err),
+        }
+    }
+
+    async fn reconnect(&mut self) -> Result<()> {
+        let mut attempt = 0;
+        let connection = loop {
+            match SplitConnection::open(&self.addr).await {
+                Ok(mut connection) => match Self::handshake(&mut connection, &self.credentials).await {
+                    Ok(()) => break connection,
+                    Err(err) if attempt >= self.backoff.max_retries => return Err(err),
+                    Err(_) => {}
+                },
+                Err(err) if attempt >= self.backoff.max_retries => return Err(err),
+                Err(_) => {}
+            }
+            sleep(self.backoff.delay_for(attempt)).await;
+            attempt += 1;
+        };
+
+        self.finish_reconnect(connection).await?;
+        self.note_channel_open(DEFAULT_CONN_CHANNEL);
+
+        Ok(())
+    }
+
+    // handshake frames are written with `write_frame_untracked`: they are
+    // connection setup, not application writes, so they must never show up
+    // in `unacked_frames` and get replayed mid-session after a reconnect
+    async fn handshake(connection: &mut SplitConnection, credentials: &HandshakeCredentials) -> Result<()> {
+        use amqp_serde::types::AmqpPeerProperties;
+
+        connection.write(&ProtocolHeader::default()).await?;
+
+        // S: 'Start'
+        connection.read_frame().await?;
+
+        // C: 'StartOk'
+        let start_ok = StartOk::new(
+            AmqpPeerProperties::new(),
+            credentials
+                .mechanism
+                .as_str()
+                .try_into()
+                .map_err(|_| io::Error::new(io::ErrorKind::InvalidInput, "invalid mechanism"))?,
+            credentials
+                .response
+                .as_str()
+                .try_into()
+                .map_err(|_| io::Error::new(io::ErrorKind::InvalidInput, "invalid response"))?,
+            credentials
+                .locale
+                .as_str()
+                .try_into()
+                .map_err(|_| io::Error::new(io::ErrorKind::InvalidInput, "invalid locale"))?,
+        );
+        connection
+            .write_frame_untracked(DEFAULT_CONN_CHANNEL, start_ok.into_frame())
+            .await?;
+
+        // S: 'Tune'
+        let (_, tune) = connection.read_frame().await?;
+        let tune = match tune {
+            Frame::Tune(_, v) => v,
+            _ => return Err(io::Error::new(io::ErrorKind::InvalidData, "expected Tune").into()),
+        };
+
+        // a `frame_max` of 0 means "no limit" per the spec; keep the
+        // configured bound rather than disabling it in that case
+        if tune.frame_max() > 0 {
+            connection.set_max_frame_size(tune.frame_max() as usize);
+        }
+
+        // C: 'TuneOk'
+        let tune_ok = TuneOk::new(tune.channel_max(), tune.frame_max(), tune.heartbeat());
+        connection
+            .write_frame_untracked(DEFAULT_CONN_CHANNEL, tune_ok.into_frame())
+            .await?;
+
+        // C: 'Open'
+        connection
+            .write_frame_untracked(DEFAULT_CONN_CHANNEL, Open::default().into_frame())
+            .await?;
+
+        // S: 'OpenOk'
+        connection.read_frame().await?;
+
+        Ok(())
+    }
 }
 
 /////////////////////////////////////////////////////////////////////////////
@@ -316,4 +990,197 @@ mod test {
         reader.close().await;
         writer.close().await.unwrap();
     }
+
+    #[tokio::test]
+    async fn test_read_frame_rejects_oversized_payload() {
+        // a peer declaring a `payload_size` above `max_frame_size` should be
+        // rejected up front, instead of `BufReader` buffering it indefinitely
+        use amqp_serde::to_buffer;
+        use bytes::BytesMut;
+        use super::{ConnectionConfig, FrameHeader};
+
+        let (mut client, mut server) = SplitConnection::pair(4096);
+
+        let oversized = (ConnectionConfig::default().max_frame_size + 1) as u32;
+        let header = FrameHeader {
+            frame_type: 1,
+            channel: DEFAULT_CONN_CHANNEL,
+            payload_size: oversized,
+        };
+        let mut bytes = BytesMut::new();
+        to_buffer(&header, &mut bytes).unwrap();
+
+        client
+            .write_raw(DEFAULT_CONN_CHANNEL, bytes.freeze())
+            .await
+            .unwrap();
+
+        assert!(server.read_frame().await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_pair_frame_roundtrip() {
+        // drive a scripted exchange over an in-memory duplex pair instead of
+        // a real broker, so this test is deterministic and needs no network.
+        let (mut client, mut server) = SplitConnection::pair(4096);
+
+        client
+            .write_frame(DEFAULT_CONN_CHANNEL, Close::default().into_frame())
+            .await
+            .unwrap();
+        let (channel_id, frame) = server.read_frame().await.unwrap();
+        assert_eq!(DEFAULT_CONN_CHANNEL, channel_id);
+        assert!(matches!(frame, Frame::Close(..)));
+    }
+
+    #[tokio::test]
+    async fn test_finish_reconnect_replays_unacked_and_notifies() {
+        use super::{HandshakeCredentials, ReconnectBackoff, ReconnectingConnection};
+        use std::sync::{Arc, Mutex};
+
+        let (client, _peer) = SplitConnection::pair(4096);
+        let (replacement, mut replacement_peer) = SplitConnection::pair(4096);
+
+        let mut connection = ReconnectingConnection {
+            addr: "unused".to_string(),
+            credentials: HandshakeCredentials {
+                mechanism: "PLAIN".to_string(),
+                response: String::new(),
+                locale: "en_US".to_string(),
+            },
+            backoff: ReconnectBackoff::default(),
+            connection: client,
+            open_channels: Vec::new(),
+            on_reconnect: None,
+        };
+
+        connection.note_channel_open(1);
+        connection.note_channel_open(2);
+        connection.note_channel_close(1);
+
+        let notified = Arc::new(Mutex::new(Vec::new()));
+        let notified_clone = notified.clone();
+        connection.on_reconnect(move |channels| {
+            *notified_clone.lock().unwrap() = channels.to_vec();
+        });
+
+        // the (fake) broker never acks this, so it must be replayed onto the
+        // replacement connection
+        connection
+            .connection
+            .write_frame(DEFAULT_CONN_CHANNEL, Close::default().into_frame())
+            .await
+            .unwrap();
+
+        connection.finish_reconnect(replacement).await.unwrap();
+
+        let (channel_id, frame) = replacement_peer.read_frame().await.unwrap();
+        assert_eq!(DEFAULT_CONN_CHANNEL, channel_id);
+        assert!(matches!(frame, Frame::Close(..)));
+        assert_eq!(vec![2], *notified.lock().unwrap());
+    }
+
+    #[tokio::test]
+    async fn test_write_frame_untracked_is_excluded_from_replay() {
+        let (mut client, _peer) = SplitConnection::pair(4096);
+
+        client
+            .write_frame_untracked(DEFAULT_CONN_CHANNEL, Close::default().into_frame())
+            .await
+            .unwrap();
+
+        assert_eq!(0, client.unacked_frames().count());
+    }
+
+    #[tokio::test]
+    async fn test_ack_frames_trims_in_flight_and_excludes_from_replay() {
+        // explicit acks (e.g. from a confirm-aware caller), not read count,
+        // are what should drop frames out of the replay set
+        let (mut client, mut peer) = SplitConnection::pair(4096);
+        tokio::spawn(async move { while peer.read_frame().await.is_ok() {} });
+
+        client
+            .write_frame(DEFAULT_CONN_CHANNEL, Close::default().into_frame())
+            .await
+            .unwrap();
+        client
+            .write_frame(DEFAULT_CONN_CHANNEL, Close::default().into_frame())
+            .await
+            .unwrap();
+        assert_eq!(2, client.unacked_frames().count());
+
+        client.ack_frames(1);
+        assert_eq!(1, client.unacked_frames().count());
+    }
+
+    #[tokio::test]
+    async fn test_write_raw_bounds_in_flight_frames() {
+        // the replay path (`write_raw`) must respect the same `max_in_flight`
+        // bound as `write_frame`, or replaying onto a connection that is
+        // already near the limit could grow `in_flight` past it. The duplex
+        // capacity comfortably covers the bytes written below, so nothing
+        // needs to drain the peer side concurrently.
+        use bytes::Bytes;
+
+        let (mut client, _peer) = SplitConnection::pair(1 << 20);
+
+        for _ in 0..4096 {
+            client
+                .write_raw(DEFAULT_CONN_CHANNEL, Bytes::from_static(b"x"))
+                .await
+                .unwrap();
+        }
+
+        assert!(client
+            .write_raw(DEFAULT_CONN_CHANNEL, Bytes::from_static(b"x"))
+            .await
+            .is_err());
+    }
+
+    #[tokio::test]
+    async fn test_write_frame_bounds_in_flight_frames() {
+        let (mut client, mut peer) = SplitConnection::pair(65536);
+        // drain the other side concurrently so the duplex's bounded capacity
+        // doesn't deadlock the write loop below; the peer still never acks,
+        // so `in_flight` on `client` keeps growing regardless
+        tokio::spawn(async move { while peer.read_frame().await.is_ok() {} });
+
+        for _ in 0..4096 {
+            client
+                .write_frame(DEFAULT_CONN_CHANNEL, Close::default().into_frame())
+                .await
+                .unwrap();
+        }
+
+        // the broker never acks any of them, so the next write should be
+        // rejected instead of growing `in_flight` without bound
+        assert!(client
+            .write_frame(DEFAULT_CONN_CHANNEL, Close::default().into_frame())
+            .await
+            .is_err());
+    }
+
+    #[tokio::test]
+    async fn test_flush_on_idle_flushes_after_coalesce_window() {
+        use std::time::Duration;
+
+        let (mut client, mut server) = SplitConnection::pair(4096);
+        client.enable_write_batching(64 * 1024, Duration::from_millis(10));
+
+        // batching mode with plenty of headroom below the high-water mark:
+        // the frame should sit buffered until the coalescing window elapses
+        client
+            .write_frame(DEFAULT_CONN_CHANNEL, Close::default().into_frame())
+            .await
+            .unwrap();
+
+        let read = tokio::time::timeout(Duration::from_millis(5), server.read_frame()).await;
+        assert!(read.is_err(), "frame should not be flushed before the coalesce window elapses");
+
+        client.flush_on_idle().await.unwrap();
+
+        let (channel_id, frame) = server.read_frame().await.unwrap();
+        assert_eq!(DEFAULT_CONN_CHANNEL, channel_id);
+        assert!(matches!(frame, Frame::Close(..)));
+    }
 }